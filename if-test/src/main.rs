@@ -4,18 +4,21 @@ use std::hint::black_box;
 use std::time::Instant;
 
 /// Tunables: bump these to turn it to 11
-const N_PRED: usize = 32; // number of base predicates (<=64 for u64)
+const N_PRED: usize = 96; // number of base predicates, up to 255 (u8 indices) across W words
 const N_RULES: usize = 64; // number of rules
 const TERMS_PER_RULE: std::ops::RangeInclusive<usize> = 2..=4;
 const REQ_PER_TERM: std::ops::RangeInclusive<usize> = 3..=6;
 const FORB_PER_TERM: std::ops::RangeInclusive<usize> = 0..=2;
 const N_EVENTS: usize = 100_000; // number of events to evaluate
 
+// number of u64 words needed to hold N_PRED bits
+const W: usize = N_PRED.div_ceil(64);
+
 // A rule is an OR of terms; each term is (all req) AND (none forb)
 #[derive(Clone)]
 struct Term {
-    req_mask: u64,
-    forb_mask: u64,
+    req_mask: [u64; W],
+    forb_mask: [u64; W],
     req_idx: Vec<u8>,
     forb_idx: Vec<u8>,
 }
@@ -25,11 +28,6 @@ struct Rule {
 }
 
 fn main() {
-    assert!(
-        N_PRED <= 64,
-        "This demo uses a single u64; raise to u128 or bitvec for more."
-    );
-
     //create reproducible rule set (same for both evaluators)
     let mut rng = SmallRng::seed_from_u64(0xB17B17);
     let rules = gen_rules(&mut rng);
@@ -42,13 +40,13 @@ fn main() {
 
     //create bitsets (to_bits) once for the mask path, we time this to be fair....
     let t_to_bits_start = Instant::now();
-    let bitsets: Vec<u64> = preds.iter().map(|row| to_bits(row)).collect();
+    let bitsets: Vec<[u64; W]> = preds.iter().map(|row| to_bits(row)).collect();
     let t_to_bits = t_to_bits_start.elapsed();
 
     //but, both evaluators must agree on a few samples
     for i in 0..5 {
         let naive = eval_rules_naive(&rules, &preds[i]);
-        let mask = eval_rules_mask(&rules, bitsets[i]);
+        let mask = eval_rules_mask(&rules, &bitsets[i]);
         println!(
             "sample {i}: naive={naive} mask={mask} equal? {}",
             naive == mask
@@ -69,37 +67,99 @@ fn main() {
     //masked evaluation (using precomputed bitsets)
     let t_mask_start = Instant::now();
     let mut count_mask = 0usize;
-    for &state in &bitsets {
+    for state in &bitsets {
         if black_box(eval_rules_mask(&rules, state)) {
             count_mask += 1;
         }
     }
     let t_mask = t_mask_start.elapsed();
 
+    //bit-sliced batch evaluation: 64 events per lane word
+    let t_batch_start = Instant::now();
+    let (matched_words, count_batch) = black_box(eval_rules_batched(&rules, &preds));
+    let t_batch = t_batch_start.elapsed();
+
+    //equivalence: the batch evaluator must land on exactly the same matched
+    //set, event for event, as the per-event masked evaluator
+    for (chunk_idx, chunk) in bitsets.chunks(64).enumerate() {
+        for (j, state) in chunk.iter().enumerate() {
+            let expected = eval_rules_mask(&rules, state);
+            let got = (matched_words[chunk_idx] >> j) & 1 != 0;
+            assert_eq!(
+                expected,
+                got,
+                "batch vs mask mismatch at event {}",
+                chunk_idx * 64 + j
+            );
+        }
+    }
+    println!("batch vs mask: verified exact match over {N_EVENTS} events");
+
     //equivalence confirmation and print timings
     println!(
         "speedup (mask vs naive): {:.1}×",
         t_naive.as_secs_f64() / t_mask.as_secs_f64()
     );
     println!(
-        "amortized cost per event: to_bits={:.3} µs, mask_eval={:.3} µs, naive={:.3} µs",
+        "speedup (batch vs mask): {:.1}×",
+        t_mask.as_secs_f64() / t_batch.as_secs_f64()
+    );
+    println!(
+        "amortized cost per event: to_bits={:.3} µs, mask_eval={:.3} µs, batch_eval={:.3} µs, naive={:.3} µs",
         1e6 * t_to_bits.as_secs_f64() / N_EVENTS as f64,
         1e6 * t_mask.as_secs_f64() / N_EVENTS as f64,
+        1e6 * t_batch.as_secs_f64() / N_EVENTS as f64,
         1e6 * t_naive.as_secs_f64() / N_EVENTS as f64
     );
     println!(
-        "events matched: naive={count_naive}  mask={count_mask}  equal? {}",
-        count_naive == count_mask
+        "events matched: naive={count_naive}  mask={count_mask}  batch={count_batch}  mask==batch? {}",
+        count_mask == count_batch
     );
-    println!("timings over {N_EVENTS} events, {N_RULES} rules, {N_PRED} predicates:");
+    println!("timings over {N_EVENTS} events, {N_RULES} rules, {N_PRED} predicates ({W} word(s)):");
     println!("  to_bits (prep once) : {:?}", t_to_bits);
     println!("  naive eval (booleans): {:?}", t_naive);
     println!("  mask  eval (bitwise) : {:?}", t_mask);
+    println!("  batch eval (bit-sliced, 64 events/word): {:?}", t_batch);
+    println!("\nTip: run with `--release`, then try N_RULES=256 or N_PRED=192 for bigger gaps (predicate indices are u8, so keep N_PRED <= 255).");
+
+    //probabilistic predicates + exact weighted model counting, on a small
+    //hand-built rule so the 2^k enumeration stays cheap
+    let probs: Vec<f64> = (0..N_PRED).map(pred_prob).collect();
+    // predicates 3 and 4 stand in for two buckets of one ordered field: a
+    // rule can never legitimately require both at once.
+    let groups: Vec<PredGroup> = vec![vec![3, 4]];
+    let policy = demo_policy_rule();
+    let p_fire = eval_rules_prob(std::slice::from_ref(&policy), &probs, &groups);
     println!(
-        "\nTip: run with `--release`, then try N_RULES=256 or N_PRED=48 (switch to u128) for bigger gaps."
+        "\nP(demo policy fires) via exact per-rule WMC, req={{0,1}} forb={{2}} OR req={{3,4}} with {{3,4}} mutually exclusive = {p_fire:.6}"
     );
 }
 
+/// a small illustrative rule for the probabilistic demo: (pred0 AND pred1
+/// AND NOT pred2) OR (pred3 AND pred4), with predicates 3 and 4 declared
+/// mutually exclusive so the second term can never actually fire.
+fn demo_policy_rule() -> Rule {
+    let req_a = vec![0u8, 1];
+    let forb_a = vec![2u8];
+    let req_b = vec![3u8, 4];
+    Rule {
+        terms: vec![
+            Term {
+                req_mask: mask_from_idx(&req_a),
+                forb_mask: mask_from_idx(&forb_a),
+                req_idx: req_a,
+                forb_idx: forb_a,
+            },
+            Term {
+                req_mask: mask_from_idx(&req_b),
+                forb_mask: [0u64; W],
+                req_idx: req_b,
+                forb_idx: vec![],
+            },
+        ],
+    }
+}
+
 /// the rulez
 
 fn gen_rules(rng: &mut SmallRng) -> Vec<Rule> {
@@ -117,14 +177,8 @@ fn gen_rule(rng: &mut SmallRng) -> Rule {
         let req_idx = sample_distinct(rng, k_req);
         let forb_idx = sample_distinct_excluding(rng, k_forb, &req_idx);
 
-        let mut req_mask = 0u64;
-        let mut forb_mask = 0u64;
-        for &i in &req_idx {
-            req_mask |= 1u64 << i;
-        }
-        for &i in &forb_idx {
-            forb_mask |= 1u64 << i;
-        }
+        let req_mask = mask_from_idx(&req_idx);
+        let forb_mask = mask_from_idx(&forb_idx);
 
         terms.push(Term {
             req_mask,
@@ -136,6 +190,14 @@ fn gen_rule(rng: &mut SmallRng) -> Rule {
     Rule { terms }
 }
 
+fn mask_from_idx(idx: &[u8]) -> [u64; W] {
+    let mut m = [0u64; W];
+    for &i in idx {
+        m[i as usize / 64] |= 1u64 << (i as usize % 64);
+    }
+    m
+}
+
 fn sample_distinct(rng: &mut SmallRng, k: usize) -> Vec<u8> {
     use rand::seq::index::sample;
     if k == 0 {
@@ -170,20 +232,25 @@ fn random_predicate_row(rng: &mut SmallRng) -> [bool; N_PRED] {
     // early bits = rarer. later bits = more common (tunable).
     let mut row = [false; N_PRED];
     for i in 0..N_PRED {
-        let p_true = 0.10 + (i as f64 / N_PRED as f64) * 0.35; // ~10%..45%
-        row[i] = rng.random_bool(p_true);
+        row[i] = rng.random_bool(pred_prob(i));
     }
     row
 }
 
+/// the same per-predicate bias `random_predicate_row` samples from, exposed
+/// so `eval_rules_prob` can reason about the population without sampling it
+fn pred_prob(i: usize) -> f64 {
+    0.10 + (i as f64 / N_PRED as f64) * 0.35 // ~10%..45%
+}
+
 /// to_bits()
 #[inline]
-fn to_bits(row: &[bool; N_PRED]) -> u64 {
-    let mut s = 0u64;
+fn to_bits(row: &[bool; N_PRED]) -> [u64; W] {
+    let mut s = [0u64; W];
     // NOTE: this loop is intentionally explicit (no iter::enumerate)
     for i in 0..N_PRED {
         if row[i] {
-            s |= 1u64 << i;
+            s[i / 64] |= 1u64 << (i % 64);
         }
     }
     s
@@ -220,12 +287,18 @@ fn eval_rules_naive(rules: &Vec<Rule>, row: &[bool; N_PRED]) -> bool {
     true
 }
 
-/// masked: 2 ANDs + 2 compares-per -term "branch-predictable"
+/// masked: 2 ANDs + 2 compares-per-term per word, short-circuiting on the
+/// first word that rules a term out
 #[inline]
-fn eval_rules_mask(rules: &Vec<Rule>, state: u64) -> bool {
+fn eval_rules_mask(rules: &Vec<Rule>, state: &[u64; W]) -> bool {
     'rule: for r in rules {
         for t in &r.terms {
-            if (state & t.req_mask) == t.req_mask && (state & t.forb_mask) == 0 {
+            let term_ok = state
+                .iter()
+                .zip(&t.req_mask)
+                .zip(&t.forb_mask)
+                .all(|((&s, &req), &forb)| (s & req) == req && (s & forb) == 0);
+            if term_ok {
                 continue 'rule;
             }
         }
@@ -233,3 +306,122 @@ fn eval_rules_mask(rules: &Vec<Rule>, state: u64) -> bool {
     }
     true
 }
+
+/// bit-sliced batch: transpose up to 64 events' predicate rows into one
+/// bit-plane per predicate (bit `j` of `plane[p]` = event `j`'s value of
+/// predicate `p`), then evaluate every term's req/forb as plane-wide AND/ANDNOT.
+#[inline]
+fn transpose_planes(rows: &[[bool; N_PRED]]) -> [u64; N_PRED] {
+    let mut planes = [0u64; N_PRED];
+    for (j, row) in rows.iter().enumerate() {
+        for (p, plane) in planes.iter_mut().enumerate() {
+            if row[p] {
+                *plane |= 1u64 << j;
+            }
+        }
+    }
+    planes
+}
+
+/// evaluate all rules against a batch of (up to 64) events' bit-planes,
+/// returning the satisfied-events mask: bit `j` set iff event `j` matched.
+#[inline]
+fn eval_rules_batch(rules: &Vec<Rule>, planes: &[u64; N_PRED]) -> u64 {
+    let mut matched = !0u64;
+    for r in rules {
+        let mut rule_lanes = 0u64;
+        for t in &r.terms {
+            let mut lanes = !0u64;
+            for &i in &t.req_idx {
+                lanes &= planes[i as usize];
+            }
+            for &i in &t.forb_idx {
+                lanes &= !planes[i as usize];
+            }
+            rule_lanes |= lanes;
+        }
+        matched &= rule_lanes;
+    }
+    matched
+}
+
+/// drive `eval_rules_batch` over the whole population, 64 events at a time;
+/// returns one matched-events bitset per batch plus the total popcount.
+fn eval_rules_batched(rules: &Vec<Rule>, preds: &[[bool; N_PRED]]) -> (Vec<u64>, usize) {
+    let mut matched_words = Vec::with_capacity(preds.len().div_ceil(64));
+    let mut popcount = 0usize;
+    for chunk in preds.chunks(64) {
+        let planes = transpose_planes(chunk);
+        let mut matched = eval_rules_batch(rules, &planes);
+        if chunk.len() < 64 {
+            // clear lanes past the real events in a short final batch
+            matched &= (1u64 << chunk.len()) - 1;
+        }
+        popcount += matched.count_ones() as usize;
+        matched_words.push(matched);
+    }
+    (matched_words, popcount)
+}
+
+/// A set of predicate indices of which at most one may be true at a time
+/// (e.g. the ordered buckets of a single thresholded field).
+type PredGroup = Vec<u8>;
+
+/// probability that every rule in `rules` fires, given each base predicate's
+/// independent probability of being true in `probs` (indexed by predicate).
+///
+/// rules are assumed independent of one another (no WMC across the whole
+/// rule set, only within each rule's own referenced variables), which is the
+/// only way exact weighted model counting stays tractable once there are
+/// dozens of rules: see `eval_rule_prob`.
+fn eval_rules_prob(rules: &[Rule], probs: &[f64], groups: &[PredGroup]) -> f64 {
+    rules.iter().map(|r| eval_rule_prob(r, probs, groups)).product()
+}
+
+/// exact weighted model count for a single rule: enumerate every assignment
+/// of the (typically <=16) predicates the rule actually references, skip
+/// assignments that set two predicates from the same mutually-exclusive
+/// group, and renormalize so the kept assignments' probabilities sum to 1.
+fn eval_rule_prob(rule: &Rule, probs: &[f64], groups: &[PredGroup]) -> f64 {
+    let mut vars: Vec<u8> = rule
+        .terms
+        .iter()
+        .flat_map(|t| t.req_idx.iter().chain(&t.forb_idx).copied())
+        .collect();
+    vars.sort_unstable();
+    vars.dedup();
+    let k = vars.len();
+    assert!(
+        k <= 24,
+        "rule references {k} distinct predicates; exact WMC enumerates 2^k and stops being tractable well before that"
+    );
+
+    let mut truth = [false; N_PRED];
+    let mut hit_mass = 0.0;
+    let mut total_mass = 0.0;
+    for assignment in 0u32..(1 << k) {
+        let mut p = 1.0;
+        for (bit, &var) in vars.iter().enumerate() {
+            let is_true = (assignment >> bit) & 1 != 0;
+            truth[var as usize] = is_true;
+            p *= if is_true { probs[var as usize] } else { 1.0 - probs[var as usize] };
+        }
+        let valid = groups
+            .iter()
+            .all(|g| g.iter().filter(|&&i| truth[i as usize]).count() <= 1);
+        if !valid {
+            continue;
+        }
+        total_mass += p;
+        if rule_fires(rule, &truth) {
+            hit_mass += p;
+        }
+    }
+    if total_mass > 0.0 { hit_mass / total_mass } else { 0.0 }
+}
+
+fn rule_fires(rule: &Rule, truth: &[bool; N_PRED]) -> bool {
+    rule.terms.iter().any(|t| {
+        t.req_idx.iter().all(|&i| truth[i as usize]) && t.forb_idx.iter().all(|&i| !truth[i as usize])
+    })
+}
@@ -0,0 +1,204 @@
+use crate::NumericFields;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A streaming reduction over events that matched some rule or policy. The
+/// core evaluation loop just calls `accumulate` for every match, so new
+/// reductions can be added without touching it.
+pub trait Aggregator<E: ?Sized> {
+    type Output;
+
+    fn accumulate(&mut self, event: &E);
+    fn finalize(self) -> Self::Output;
+}
+
+/// number of matched events
+#[derive(Default)]
+pub struct Count(u64);
+
+impl Count {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<E: ?Sized> Aggregator<E> for Count {
+    type Output = u64;
+
+    fn accumulate(&mut self, _event: &E) {
+        self.0 += 1;
+    }
+
+    fn finalize(self) -> u64 {
+        self.0
+    }
+}
+
+/// running total of a named numeric field
+pub struct Sum {
+    field: String,
+    total: f64,
+}
+
+impl Sum {
+    pub fn new(field: impl Into<String>) -> Self {
+        Self { field: field.into(), total: 0.0 }
+    }
+}
+
+impl<E: NumericFields + ?Sized> Aggregator<E> for Sum {
+    type Output = f64;
+
+    fn accumulate(&mut self, event: &E) {
+        if let Some(v) = event.numeric_field(&self.field) {
+            self.total += v;
+        }
+    }
+
+    fn finalize(self) -> f64 {
+        self.total
+    }
+}
+
+/// running average of a named numeric field
+pub struct Avg {
+    field: String,
+    total: f64,
+    count: u64,
+}
+
+impl Avg {
+    pub fn new(field: impl Into<String>) -> Self {
+        Self { field: field.into(), total: 0.0, count: 0 }
+    }
+}
+
+impl<E: NumericFields + ?Sized> Aggregator<E> for Avg {
+    type Output = f64;
+
+    fn accumulate(&mut self, event: &E) {
+        if let Some(v) = event.numeric_field(&self.field) {
+            self.total += v;
+            self.count += 1;
+        }
+    }
+
+    fn finalize(self) -> f64 {
+        if self.count == 0 { 0.0 } else { self.total / self.count as f64 }
+    }
+}
+
+/// smallest value seen for a named numeric field
+pub struct Min {
+    field: String,
+    min: Option<f64>,
+}
+
+impl Min {
+    pub fn new(field: impl Into<String>) -> Self {
+        Self { field: field.into(), min: None }
+    }
+}
+
+impl<E: NumericFields + ?Sized> Aggregator<E> for Min {
+    type Output = Option<f64>;
+
+    fn accumulate(&mut self, event: &E) {
+        if let Some(v) = event.numeric_field(&self.field) {
+            self.min = Some(self.min.map_or(v, |m| m.min(v)));
+        }
+    }
+
+    fn finalize(self) -> Option<f64> {
+        self.min
+    }
+}
+
+/// largest value seen for a named numeric field
+pub struct Max {
+    field: String,
+    max: Option<f64>,
+}
+
+impl Max {
+    pub fn new(field: impl Into<String>) -> Self {
+        Self { field: field.into(), max: None }
+    }
+}
+
+impl<E: NumericFields + ?Sized> Aggregator<E> for Max {
+    type Output = Option<f64>;
+
+    fn accumulate(&mut self, event: &E) {
+        if let Some(v) = event.numeric_field(&self.field) {
+            self.max = Some(self.max.map_or(v, |m| m.max(v)));
+        }
+    }
+
+    fn finalize(self) -> Option<f64> {
+        self.max
+    }
+}
+
+struct HeapEntry<E> {
+    key: f64,
+    event: E,
+}
+
+impl<E> PartialEq for HeapEntry<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+impl<E> Eq for HeapEntry<E> {}
+
+impl<E> PartialOrd for HeapEntry<E> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<E> Ord for HeapEntry<E> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.total_cmp(&other.key)
+    }
+}
+
+/// the `k` matched events with the largest named numeric field, retained via
+/// a bounded min-heap so memory stays O(k) regardless of stream length
+pub struct TopK<E> {
+    field: String,
+    k: usize,
+    heap: BinaryHeap<Reverse<HeapEntry<E>>>,
+}
+
+impl<E> TopK<E> {
+    pub fn new(field: impl Into<String>, k: usize) -> Self {
+        Self { field: field.into(), k, heap: BinaryHeap::with_capacity(k) }
+    }
+}
+
+impl<E: NumericFields + Clone> Aggregator<E> for TopK<E> {
+    type Output = Vec<(f64, E)>;
+
+    fn accumulate(&mut self, event: &E) {
+        let Some(key) = event.numeric_field(&self.field) else { return };
+        if self.k == 0 {
+            return;
+        }
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(HeapEntry { key, event: event.clone() }));
+        } else if let Some(Reverse(smallest)) = self.heap.peek() {
+            if key > smallest.key {
+                self.heap.pop();
+                self.heap.push(Reverse(HeapEntry { key, event: event.clone() }));
+            }
+        }
+    }
+
+    fn finalize(self) -> Vec<(f64, E)> {
+        let mut out: Vec<(f64, E)> =
+            self.heap.into_iter().map(|Reverse(e)| (e.key, e.event)).collect();
+        out.sort_by(|a, b| b.0.total_cmp(&a.0));
+        out
+    }
+}
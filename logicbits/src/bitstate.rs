@@ -0,0 +1,102 @@
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+/// A predicate bitset spanning `W` words of 64 bits each, i.e. up to
+/// `64 * W` atomic facts. Predicate `i` lives in word `i / 64`, bit `i % 64`.
+///
+/// This is the multi-word successor to the old bare-`u64` bitset: once a
+/// struct accumulates more than 64 `#[yuck(...)]` predicates the derive picks
+/// `W = ceil(n_pred / 64)` and every generated mask grows to match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitState<const W: usize>(pub [u64; W]);
+
+impl<const W: usize> Default for BitState<W> {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl<const W: usize> BitState<W> {
+    pub const ZERO: Self = Self([0u64; W]);
+
+    #[inline]
+    pub fn set(&mut self, bit: usize) {
+        self.0[bit / 64] |= 1u64 << (bit % 64);
+    }
+
+    #[inline]
+    pub fn get(&self, bit: usize) -> bool {
+        (self.0[bit / 64] >> (bit % 64)) & 1 != 0
+    }
+
+    /// `(self & req) == req && (self & forb) == 0`, word by word, bailing out
+    /// of the comparison as soon as one word rules the term out.
+    #[inline]
+    pub fn satisfies(&self, req: &Self, forb: &Self) -> bool {
+        for w in 0..W {
+            if (self.0[w] & req.0[w]) != req.0[w] || (self.0[w] & forb.0[w]) != 0 {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl<const W: usize> BitAnd for BitState<W> {
+    type Output = Self;
+    #[inline]
+    fn bitand(mut self, rhs: Self) -> Self {
+        self &= rhs;
+        self
+    }
+}
+
+impl<const W: usize> BitAndAssign for BitState<W> {
+    #[inline]
+    fn bitand_assign(&mut self, rhs: Self) {
+        for (lhs, rhs) in self.0.iter_mut().zip(rhs.0) {
+            *lhs &= rhs;
+        }
+    }
+}
+
+impl<const W: usize> BitOr for BitState<W> {
+    type Output = Self;
+    #[inline]
+    fn bitor(mut self, rhs: Self) -> Self {
+        self |= rhs;
+        self
+    }
+}
+
+impl<const W: usize> BitOrAssign for BitState<W> {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Self) {
+        for (lhs, rhs) in self.0.iter_mut().zip(rhs.0) {
+            *lhs |= rhs;
+        }
+    }
+}
+
+impl<const W: usize> Not for BitState<W> {
+    type Output = Self;
+    #[inline]
+    fn not(mut self) -> Self {
+        for word in self.0.iter_mut() {
+            *word = !*word;
+        }
+        self
+    }
+}
+
+impl<const W: usize> fmt::Binary for BitState<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (w, word) in self.0.iter().enumerate().rev() {
+            if w != W - 1 {
+                write!(f, "_")?;
+            }
+            write!(f, "{word:064b}")?;
+        }
+        Ok(())
+    }
+}
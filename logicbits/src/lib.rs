@@ -0,0 +1,21 @@
+mod aggregate;
+mod bitstate;
+mod policy;
+
+pub use aggregate::{Aggregator, Avg, Count, Max, Min, Sum, TopK};
+pub use bitstate::BitState;
+pub use logicbits_macros::KitchenNightmares;
+pub use policy::{CompiledPolicy, NameRegistry, PolicyNode, PolicySet, PolicySpec, UnknownPred};
+
+/// Implemented by `#[derive(KitchenNightmares)]` types: projects a struct's
+/// `#[yuck(...)]`-tagged fields onto a `W`-word predicate bitset.
+pub trait ToBits<const W: usize> {
+    fn to_bits(&self) -> BitState<W>;
+}
+
+/// Implemented by `#[derive(KitchenNightmares)]` types: exposes their
+/// `serve`/`largeness`-tagged fields by name so an `Aggregator` can pull a
+/// numeric value out of a matched event without knowing its concrete type.
+pub trait NumericFields {
+    fn numeric_field(&self, name: &str) -> Option<f64>;
+}
@@ -0,0 +1,196 @@
+use crate::BitState;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+/// Implemented by `#[derive(KitchenNightmares)]` types: resolves a registered
+/// predicate name to its mask, without needing an instance of the event.
+/// The derive implements this with the same name-to-mask table it emits
+/// for its inherent `pred_mask_by_name` associated function.
+pub trait NameRegistry<const W: usize> {
+    fn pred_mask_by_name(name: &str) -> Option<BitState<W>>;
+
+    /// The names of every predicate sharing a mutually-exclusive group with
+    /// `name` (including `name` itself) — e.g. the buckets of a
+    /// `largeness(mode = "bucket", ...)` field — if it belongs to one.
+    /// Types with no bucket groups can rely on this default.
+    fn pred_group(_name: &str) -> Option<&'static [&'static str]> {
+        None
+    }
+}
+
+/// A boolean expression over registered predicate names. `PolicySpec` holds
+/// one of these as the condition under which it matches an event. Derives
+/// `Deserialize` in its default (externally tagged) form, e.g.
+/// `{"All": [{"Pred": "DINER_ACME"}, {"Not": {"Pred": "BIG_GROUP"}}]}`, so
+/// policies can be authored in JSON/YAML with the full expressiveness of
+/// the internal `Rule` type.
+#[derive(Debug, Clone, Deserialize)]
+pub enum PolicyNode {
+    Pred(String),
+    All(Vec<PolicyNode>),
+    Any(Vec<PolicyNode>),
+    Not(Box<PolicyNode>),
+}
+
+/// A named policy: a human-facing condition (e.g. loaded from JSON/YAML)
+/// that `CompiledPolicy::compile` resolves against a concrete event type's
+/// registered predicate names.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PolicySpec {
+    pub name: String,
+    pub expr: PolicyNode,
+}
+
+impl PolicySpec {
+    /// The old flat shorthand: every name in `all` required, every name in
+    /// `none` forbidden, ANDed together.
+    pub fn flat(name: impl Into<String>, all: Vec<String>, none: Vec<String>) -> Self {
+        let mut terms: Vec<PolicyNode> = all.into_iter().map(PolicyNode::Pred).collect();
+        terms.extend(none.into_iter().map(|p| PolicyNode::Not(Box::new(PolicyNode::Pred(p)))));
+        Self { name: name.into(), expr: PolicyNode::All(terms) }
+    }
+}
+
+/// One OR-of-AND term, named rather than bit-resolved yet: the set of
+/// predicate names required present and the set required absent.
+struct NamedTerm {
+    req: Vec<String>,
+    forb: Vec<String>,
+}
+
+/// Expands `node` into an equivalent OR-of-AND normal form, pushing any
+/// negation down to individual predicate literals along the way (so a
+/// `Not` ever only flips which side of a `NamedTerm` a name lands on).
+fn to_dnf(node: &PolicyNode, negated: bool) -> Vec<NamedTerm> {
+    match node {
+        PolicyNode::Pred(name) if !negated => {
+            vec![NamedTerm { req: vec![name.clone()], forb: vec![] }]
+        }
+        PolicyNode::Pred(name) => vec![NamedTerm { req: vec![], forb: vec![name.clone()] }],
+        PolicyNode::Not(inner) => to_dnf(inner, !negated),
+        // AND distributes into a cartesian product of its children's terms;
+        // negated, De Morgan turns it into the OR of its negated children.
+        PolicyNode::All(children) if !negated => {
+            cartesian_and(children.iter().map(|c| to_dnf(c, false)).collect())
+        }
+        PolicyNode::All(children) => children.iter().flat_map(|c| to_dnf(c, true)).collect(),
+        // OR is the reverse: normally a flat union of its children's terms,
+        // negated it becomes the AND (cartesian product) of its negated children.
+        PolicyNode::Any(children) if !negated => {
+            children.iter().flat_map(|c| to_dnf(c, false)).collect()
+        }
+        PolicyNode::Any(children) => cartesian_and(children.iter().map(|c| to_dnf(c, true)).collect()),
+    }
+}
+
+/// Cartesian product across `branches`, merging each combination's
+/// req/forb name lists. An empty branch (an unsatisfiable child) poisons
+/// the whole product, same as `AND` with a `false` operand.
+fn cartesian_and(branches: Vec<Vec<NamedTerm>>) -> Vec<NamedTerm> {
+    branches.into_iter().fold(vec![NamedTerm { req: vec![], forb: vec![] }], |acc, branch| {
+        let mut out = Vec::with_capacity(acc.len() * branch.len());
+        for a in &acc {
+            for b in &branch {
+                let mut req = a.req.clone();
+                req.extend(b.req.iter().cloned());
+                let mut forb = a.forb.clone();
+                forb.extend(b.forb.iter().cloned());
+                out.push(NamedTerm { req, forb });
+            }
+        }
+        out
+    })
+}
+
+/// One or more predicate names in a `PolicySpec` couldn't be resolved
+/// against the event type's registry. Carries every offending name, not
+/// just the first one hit.
+#[derive(Debug)]
+pub struct UnknownPred(pub Vec<String>);
+
+impl std::fmt::Display for UnknownPred {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown predicate name(s): {}", self.0.join(", "))
+    }
+}
+
+impl std::error::Error for UnknownPred {}
+
+/// A `PolicySpec` resolved against a concrete event type: its boolean DSL
+/// has already been flattened to OR-of-AND `(req, forb)` masks, so matching
+/// an event is just `BitState::satisfies` over each term.
+pub struct CompiledPolicy<const W: usize> {
+    pub name: String,
+    terms: Vec<(BitState<W>, BitState<W>)>,
+}
+
+impl<const W: usize> CompiledPolicy<W> {
+    /// Resolves every predicate name `spec` references against `R`'s
+    /// registry, collecting *all* unknown names before failing so a bad
+    /// policy file reports every typo in one pass.
+    pub fn compile<R: NameRegistry<W>>(spec: &PolicySpec, _registry: &R) -> Result<Self, UnknownPred> {
+        let named_terms = to_dnf(&spec.expr, false);
+        let mut unknown = BTreeSet::new();
+        let mut terms = Vec::with_capacity(named_terms.len());
+
+        for term in &named_terms {
+            let mut req = BitState::ZERO;
+            let mut forb = BitState::ZERO;
+            let mut resolved = true;
+
+            for name in &term.req {
+                match R::pred_mask_by_name(name) {
+                    Some(mask) => req |= mask,
+                    None => {
+                        unknown.insert(name.clone());
+                        resolved = false;
+                    }
+                }
+            }
+            for name in &term.forb {
+                match R::pred_mask_by_name(name) {
+                    Some(mask) => forb |= mask,
+                    None => {
+                        unknown.insert(name.clone());
+                        resolved = false;
+                    }
+                }
+            }
+
+            if resolved {
+                terms.push((req, forb));
+            }
+        }
+
+        if !unknown.is_empty() {
+            return Err(UnknownPred(unknown.into_iter().collect()));
+        }
+
+        Ok(Self { name: spec.name.clone(), terms })
+    }
+
+    pub fn matches(&self, bits: &BitState<W>) -> bool {
+        self.terms.iter().any(|(req, forb)| bits.satisfies(req, forb))
+    }
+}
+
+/// Many compiled policies evaluated together against the same event.
+#[derive(Default)]
+pub struct PolicySet<const W: usize> {
+    policies: Vec<CompiledPolicy<W>>,
+}
+
+impl<const W: usize> PolicySet<W> {
+    pub fn new() -> Self {
+        Self { policies: Vec::new() }
+    }
+
+    pub fn push(&mut self, policy: CompiledPolicy<W>) {
+        self.policies.push(policy);
+    }
+
+    /// Names of every policy in the set that `bits` satisfies.
+    pub fn matches(&self, bits: &BitState<W>) -> Vec<&str> {
+        self.policies.iter().filter(|p| p.matches(bits)).map(|p| p.name.as_str()).collect()
+    }
+}
@@ -1,7 +1,19 @@
 #[derive(Debug)]
 pub enum FieldAttr {
-    Diner { pred: String },
-    Kitchen { eq: String, pred: String },
-    Largeness { pred_prefix: String, heat: Vec<u32> },
+    Diner { eq: String, pred: String },
+    Kitchen { pred: String },
+    Largeness { pred_prefix: String, heat: Vec<u32>, mode: LargenessMode },
     Serve { pred_ns: String },
 }
+
+/// How a `largeness` field's thresholds turn into predicate bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LargenessMode {
+    /// cumulative "at least": every `pred_prefix{t}` with `t <= value` is set
+    Ge,
+    /// cumulative "at most": every `pred_prefix{t}` with `value <= t` is set
+    Le,
+    /// exactly one predicate for the interval `value` falls in; the whole
+    /// set is registered as a mutually-exclusive group
+    Bucket,
+}
@@ -0,0 +1,296 @@
+use crate::data_objects::{FieldAttr, LargenessMode};
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{Data, DeriveInput, Expr, ExprLit, Fields, Lit, Meta, MetaList, Token};
+
+pub fn compile_error(span: Span, msg: &str) -> TokenStream2 {
+    syn::Error::new(span, msg).to_compile_error()
+}
+
+/// One allocated predicate bit: its registered name and the expression (in
+/// terms of `self`) that decides whether it's set.
+struct PredSlot {
+    name: String,
+    cond: TokenStream2,
+}
+
+pub fn generate_for_kitchen(ast: &DeriveInput) -> TokenStream2 {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let fields = match &ast.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(f) => &f.named,
+            _ => return compile_error(ast.ident.span(), "KitchenNightmares requires named fields"),
+        },
+        _ => return compile_error(ast.ident.span(), "KitchenNightmares only supports structs"),
+    };
+
+    let kitchen_menu = match parse_kitchen_menu(&ast.attrs) {
+        Ok(v) => v,
+        Err(e) => return e.to_compile_error(),
+    };
+
+    let mut slots: Vec<PredSlot> = Vec::new();
+    let mut numeric_fields: Vec<TokenStream2> = Vec::new();
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    for field in fields {
+        let field_ident = field.ident.as_ref().expect("named field");
+        for attr in &field.attrs {
+            if !attr.path().is_ident("yuck") {
+                continue;
+            }
+            let parsed = match parse_field_attr(attr) {
+                Ok(p) => p,
+                Err(e) => return e.to_compile_error(),
+            };
+            match parsed {
+                FieldAttr::Diner { eq, pred } => slots.push(PredSlot {
+                    name: pred,
+                    cond: quote! { self.#field_ident == #eq },
+                }),
+                FieldAttr::Kitchen { pred } => slots.push(PredSlot {
+                    name: pred,
+                    cond: quote! { self.#field_ident },
+                }),
+                FieldAttr::Serve { pred_ns } => {
+                    slots.push(PredSlot {
+                        name: pred_ns,
+                        cond: quote! { self.#field_ident == 0 },
+                    });
+                    let field_name = field_ident.to_string();
+                    numeric_fields.push(quote! {
+                        #field_name => Some(self.#field_ident as f64)
+                    });
+                }
+                FieldAttr::Largeness { pred_prefix, mut heat, mode } => {
+                    heat.sort_unstable();
+                    match mode {
+                        LargenessMode::Ge => {
+                            for threshold in &heat {
+                                slots.push(PredSlot {
+                                    name: format!("{pred_prefix}{threshold}"),
+                                    cond: quote! { self.#field_ident as u64 >= #threshold as u64 },
+                                });
+                            }
+                        }
+                        LargenessMode::Le => {
+                            for threshold in &heat {
+                                slots.push(PredSlot {
+                                    name: format!("{pred_prefix}{threshold}"),
+                                    cond: quote! { self.#field_ident as u64 <= #threshold as u64 },
+                                });
+                            }
+                        }
+                        LargenessMode::Bucket => {
+                            // each bucket covers [threshold, next threshold), the
+                            // last one is unbounded above; the whole set is a
+                            // mutually-exclusive group since at most one fires
+                            // (none for a value below the lowest threshold)
+                            let names: Vec<String> =
+                                heat.iter().map(|t| format!("{pred_prefix}{t}")).collect();
+                            for (i, threshold) in heat.iter().enumerate() {
+                                let cond = match heat.get(i + 1) {
+                                    Some(next) => quote! {
+                                        self.#field_ident as u64 >= #threshold as u64
+                                            && (self.#field_ident as u64) < #next as u64
+                                    },
+                                    None => quote! { self.#field_ident as u64 >= #threshold as u64 },
+                                };
+                                slots.push(PredSlot { name: names[i].clone(), cond });
+                            }
+                            groups.push(names);
+                        }
+                    }
+                    let field_name = field_ident.to_string();
+                    numeric_fields.push(quote! {
+                        #field_name => Some(self.#field_ident as f64)
+                    });
+                }
+            }
+        }
+    }
+
+    let width = slots.len().div_ceil(64).max(1);
+
+    let set_bits = slots.iter().enumerate().map(|(i, slot)| {
+        let cond = &slot.cond;
+        let word = i / 64;
+        let bit = i % 64;
+        quote! {
+            if #cond {
+                bits.0[#word] |= 1u64 << #bit;
+            }
+        }
+    });
+
+    let match_arms: Vec<TokenStream2> = slots
+        .iter()
+        .enumerate()
+        .map(|(i, slot)| {
+            let pred_name = &slot.name;
+            let word = i / 64;
+            let bit = i % 64;
+            quote! {
+                #pred_name => {
+                    let mut w = [0u64; #width];
+                    w[#word] |= 1u64 << #bit;
+                    Some(::logicbits::BitState(w))
+                }
+            }
+        })
+        .collect();
+
+    let mut group_arms: Vec<TokenStream2> = Vec::new();
+    for group in &groups {
+        let members = group;
+        for name in group {
+            group_arms.push(quote! {
+                #name => Some(&[#(#members),*] as &[&str])
+            });
+        }
+    }
+
+    let kitchen_menu_const = format_ident!("KITCHEN_MENU");
+
+    quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub const #kitchen_menu_const: u32 = #kitchen_menu;
+
+            pub fn pred_mask_by_name(name: &str) -> Option<::logicbits::BitState<#width>> {
+                match name {
+                    #(#match_arms,)*
+                    _ => None,
+                }
+            }
+
+            /// The names of every predicate in `name`'s mutually-exclusive
+            /// bucket group (including `name` itself), if it belongs to one.
+            pub fn pred_group(name: &str) -> Option<&'static [&'static str]> {
+                match name {
+                    #(#group_arms,)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl #impl_generics ::logicbits::NameRegistry<#width> for #name #ty_generics #where_clause {
+            fn pred_mask_by_name(name: &str) -> Option<::logicbits::BitState<#width>> {
+                match name {
+                    #(#match_arms,)*
+                    _ => None,
+                }
+            }
+
+            fn pred_group(name: &str) -> Option<&'static [&'static str]> {
+                match name {
+                    #(#group_arms,)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl #impl_generics ::logicbits::ToBits<#width> for #name #ty_generics #where_clause {
+            fn to_bits(&self) -> ::logicbits::BitState<#width> {
+                let mut bits = ::logicbits::BitState([0u64; #width]);
+                #(#set_bits)*
+                bits
+            }
+        }
+
+        impl #impl_generics ::logicbits::NumericFields for #name #ty_generics #where_clause {
+            fn numeric_field(&self, name: &str) -> Option<f64> {
+                match name {
+                    #(#numeric_fields,)*
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+fn parse_kitchen_menu(attrs: &[syn::Attribute]) -> syn::Result<u32> {
+    for attr in attrs {
+        if !attr.path().is_ident("yuck") {
+            continue;
+        }
+        let inner: Meta = attr.parse_args()?;
+        if let Meta::NameValue(nv) = &inner {
+            if nv.path.is_ident("kitchen_menu") {
+                return match &nv.value {
+                    Expr::Lit(ExprLit { lit: Lit::Int(i), .. }) => i.base10_parse(),
+                    other => Err(syn::Error::new_spanned(other, "kitchen_menu expects an integer")),
+                };
+            }
+        }
+    }
+    Err(syn::Error::new(
+        Span::call_site(),
+        "KitchenNightmares requires #[yuck(kitchen_menu = N)] on the struct",
+    ))
+}
+
+fn parse_field_attr(attr: &syn::Attribute) -> syn::Result<FieldAttr> {
+    let inner: Meta = attr.parse_args()?;
+    let list = match &inner {
+        Meta::List(list) => list,
+        other => return Err(syn::Error::new_spanned(other, "expected e.g. diner(eq = \"...\", pred = \"...\")")),
+    };
+    let kv = nested_kv(list)?;
+    let get = |key: &str| -> syn::Result<String> {
+        kv.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .ok_or_else(|| syn::Error::new_spanned(list, format!("missing `{key}`")))
+    };
+
+    if list.path.is_ident("diner") {
+        Ok(FieldAttr::Diner { eq: get("eq")?, pred: get("pred")? })
+    } else if list.path.is_ident("kitchen") {
+        Ok(FieldAttr::Kitchen { pred: get("pred")? })
+    } else if list.path.is_ident("serve") {
+        Ok(FieldAttr::Serve { pred_ns: get("pred_ns")? })
+    } else if list.path.is_ident("largeness") {
+        let heat = get("heat")?
+            .split(',')
+            .map(|s| s.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| syn::Error::new_spanned(list, format!("invalid `heat` list: {e}")))?;
+        let mode = match kv.iter().find(|(k, _)| k == "mode") {
+            None => LargenessMode::Ge,
+            Some((_, v)) if v == "ge" => LargenessMode::Ge,
+            Some((_, v)) if v == "le" => LargenessMode::Le,
+            Some((_, v)) if v == "bucket" => LargenessMode::Bucket,
+            Some((_, v)) => {
+                return Err(syn::Error::new_spanned(
+                    list,
+                    format!("invalid `mode` (expected ge/le/bucket, got `{v}`)"),
+                ));
+            }
+        };
+        Ok(FieldAttr::Largeness { pred_prefix: get("pred_prefix")?, heat, mode })
+    } else {
+        Err(syn::Error::new_spanned(&list.path, "unknown yuck field attribute"))
+    }
+}
+
+fn nested_kv(list: &MetaList) -> syn::Result<Vec<(String, String)>> {
+    let pairs = list.parse_args_with(Punctuated::<syn::MetaNameValue, Token![,]>::parse_terminated)?;
+    pairs
+        .into_iter()
+        .map(|nv| {
+            let key = nv
+                .path
+                .get_ident()
+                .map(|i| i.to_string())
+                .ok_or_else(|| syn::Error::new_spanned(&nv, "expected identifier key"))?;
+            let value = match &nv.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) => s.value(),
+                Expr::Lit(ExprLit { lit: Lit::Int(i), .. }) => i.base10_digits().to_string(),
+                other => return Err(syn::Error::new_spanned(other, "expected a string or integer literal")),
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
@@ -1,7 +1,8 @@
-use logicbits::{KitchenNightmares, ToBits};
-use serde::Deserialize;
+use logicbits::{
+    Aggregator, CompiledPolicy, Count, KitchenNightmares, PolicyNode, PolicySet, PolicySpec, Sum, ToBits, TopK,
+};
 
-#[derive(KitchenNightmares)]
+#[derive(Clone, KitchenNightmares)]
 #[yuck(kitchen_menu = 1)]
 pub struct Event<'a> {
     #[yuck(diner(eq = "acme", pred = "DINER_ACME"))]
@@ -13,15 +14,11 @@ pub struct Event<'a> {
     #[yuck(serve(pred_ns = "NO_SERVE"))]
     serve: u16,
 
-    #[yuck(largeness(pred_prefix = "LRGNSS_ORDER_", heat = "100,200,600"))]
+    #[yuck(largeness(pred_prefix = "LRGNSS_ORDER_", heat = "100,200,600", mode = "ge"))]
     heat: u32,
-}
 
-#[derive(Deserialize)]
-struct PolicySpec {
-    name: String,
-    all: Vec<String>,
-    none: Vec<String>,
+    #[yuck(largeness(pred_prefix = "PARTY_", heat = "2,4,8", mode = "bucket"))]
+    party_size: u32,
 }
 
 fn main() {
@@ -30,6 +27,7 @@ fn main() {
         big_group: false,
         serve: 200,
         heat: 230,
+        party_size: 5,
     };
 
     let bits = e.to_bits();
@@ -43,21 +41,69 @@ fn main() {
         Event::pred_mask_by_name("BIG_GROUP").is_some(),
     );
 
-    let spec = PolicySpec {
-        name: "heat_warn".into(),
-        all: vec!["DINER_ACME".into(), "LRGNSS_ORDER_200".into()],
-        none: vec!["BIG_GROUP".into()],
+    // a party_size of 5 falls in the [4, 8) bucket, and the bucket's other
+    // predicates are registered as its mutually-exclusive group
+    println!(
+        "party bucket? PARTY_4={:?} PARTY_2={:?} PARTY_8={:?} group(PARTY_4)={:?}",
+        Event::pred_mask_by_name("PARTY_4").map(|m| bits.satisfies(&m, &Default::default())),
+        Event::pred_mask_by_name("PARTY_2").map(|m| bits.satisfies(&m, &Default::default())),
+        Event::pred_mask_by_name("PARTY_8").map(|m| bits.satisfies(&m, &Default::default())),
+        Event::pred_group("PARTY_4"),
+    );
+
+    // the flat all/none shorthand compiles to the same OR-of-AND form as a
+    // hand-built PolicyNode tree
+    let heat_warn_spec =
+        PolicySpec::flat("heat_warn", vec!["DINER_ACME".into(), "LRGNSS_ORDER_200".into()], vec!["BIG_GROUP".into()]);
+    let heat_warn = CompiledPolicy::compile(&heat_warn_spec, &e).expect("heat_warn should compile");
+
+    // a policy using the recursive any/all/not DSL: acme diners who are
+    // either a big group or ordering enough to cross the 600 threshold, but
+    // not ones we've already flagged for no-show risk
+    let escalate_spec = PolicySpec {
+        name: "escalate".into(),
+        expr: PolicyNode::All(vec![
+            PolicyNode::Pred("DINER_ACME".into()),
+            PolicyNode::Any(vec![
+                PolicyNode::Pred("BIG_GROUP".into()),
+                PolicyNode::Pred("LRGNSS_ORDER_600".into()),
+            ]),
+            PolicyNode::Not(Box::new(PolicyNode::Pred("NO_SERVE".into()))),
+        ]),
     };
+    let escalate = CompiledPolicy::compile(&escalate_spec, &e).expect("escalate should compile");
 
-    let mut req = 0u64;
-    let mut forb = 0u64;
-    for n in spec.all {
-        req |= Event::pred_mask_by_name(&n).expect("unknown pred");
-    }
-    for n in spec.none {
-        forb |= Event::pred_mask_by_name(&n).expect("unknown pred");
-    }
+    let mut policies = PolicySet::new();
+    policies.push(heat_warn);
+    policies.push(escalate);
+    println!("matched policies: {:?}", policies.matches(&bits));
+
+    // streaming aggregation over a stream of events matching a policy:
+    // count, total heat, and the top 2 events by heat
+    let stream = [
+        Event { diner: "acme", big_group: false, serve: 150, heat: 120, party_size: 2 },
+        Event { diner: "acme", big_group: false, serve: 180, heat: 610, party_size: 6 },
+        Event { diner: "acme", big_group: true, serve: 90, heat: 340, party_size: 9 },
+        Event { diner: "acme", big_group: false, serve: 0, heat: 205, party_size: 3 },
+    ];
 
-    let matched = (bits & req) == req && (bits & forb) == 0;
-    println!("matched '{}'? {}", spec.name, matched);
+    let mut count = Count::new();
+    let mut heat_total = Sum::new("heat");
+    let mut top_heat = TopK::new("heat", 2);
+    for e in &stream {
+        if policies.matches(&e.to_bits()).contains(&"heat_warn") {
+            count.accumulate(e);
+            heat_total.accumulate(e);
+            top_heat.accumulate(e);
+        }
+    }
+    println!(
+        "policy 'heat_warn': matched={} total_heat={} top_heat={:?}",
+        Aggregator::<Event>::finalize(count),
+        Aggregator::<Event>::finalize(heat_total),
+        Aggregator::<Event>::finalize(top_heat)
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect::<Vec<_>>(),
+    );
 }